@@ -0,0 +1,62 @@
+use std::pin::Pin;
+use tokio::io::AsyncBufRead;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Gzip,
+    Zstd,
+    Xz,
+    Bzip2,
+}
+
+impl Codec {
+    // auto-detect from the object key's suffix, used when the caller
+    // hasn't forced a codec via `with_decompression`
+    pub(crate) fn from_key(key: &str) -> Option<Self> {
+        if key.ends_with(".gz") || key.ends_with(".gzip") {
+            Some(Codec::Gzip)
+        } else if key.ends_with(".zst") || key.ends_with(".zstd") {
+            Some(Codec::Zstd)
+        } else if key.ends_with(".xz") {
+            Some(Codec::Xz)
+        } else if key.ends_with(".bz2") {
+            Some(Codec::Bzip2)
+        } else {
+            None
+        }
+    }
+}
+
+// wraps `reader` in the streaming decoder matching `codec`, so decompression
+// happens incrementally instead of buffering the whole object first
+pub(crate) fn decode<'a, R>(codec: Codec, reader: R) -> Pin<Box<dyn AsyncBufRead + 'a>>
+where
+    R: AsyncBufRead + Unpin + 'a,
+{
+    use async_compression::tokio::bufread::{GzipDecoder, ZstdDecoder, XzDecoder, BzDecoder};
+    use tokio::io::BufReader;
+
+    match codec {
+        Codec::Gzip => Box::pin(BufReader::new(GzipDecoder::new(reader))),
+        Codec::Zstd => Box::pin(BufReader::new(ZstdDecoder::new(reader))),
+        Codec::Xz => Box::pin(BufReader::new(XzDecoder::new(reader))),
+        Codec::Bzip2 => Box::pin(BufReader::new(BzDecoder::new(reader))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_key() {
+        assert_eq!(Codec::from_key("data.gz"), Some(Codec::Gzip));
+        assert_eq!(Codec::from_key("data.gzip"), Some(Codec::Gzip));
+        assert_eq!(Codec::from_key("data.zst"), Some(Codec::Zstd));
+        assert_eq!(Codec::from_key("data.zstd"), Some(Codec::Zstd));
+        assert_eq!(Codec::from_key("data.xz"), Some(Codec::Xz));
+        assert_eq!(Codec::from_key("data.bz2"), Some(Codec::Bzip2));
+        assert_eq!(Codec::from_key("data.txt"), None);
+        assert_eq!(Codec::from_key("data"), None);
+    }
+}
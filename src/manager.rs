@@ -1,17 +1,25 @@
+use std::cell::RefCell;
 use std::path::Path;
+use std::rc::Rc;
 use tokio::io::{Error, ErrorKind};
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use log::trace;
 use aws_sdk_s3::Client as S3Client;
 use aws_sdk_s3::operation::get_object::GetObjectError;
+use aws_sdk_s3::types::{CompletedPart, CompletedMultipartUpload};
+use aws_smithy_types::byte_stream::ByteStream;
+use futures::stream::{self, StreamExt};
 use crate::uri::S3Uri;
 use crate::stream::ByteStreamProgress;
+use crate::codec::{self, Codec};
 
 pub struct S3TransferConfig {
     max_concurrency: usize,
     br_threshold: usize,
     mp_threshold: usize,
     mp_chunk_size: usize,
+    max_retry_attempts: usize,
+    base_timeout: std::time::Duration,
 }
 
 impl S3TransferConfig {
@@ -21,9 +29,57 @@ impl S3TransferConfig {
             br_threshold: usize::MAX,
             mp_threshold: usize::MAX,
             mp_chunk_size: 1_048_576, // 1MiB
+            max_retry_attempts: 5,
+            base_timeout: std::time::Duration::from_secs(15),
         }
     }
 
+    pub fn with_max_retry_attempts(mut self, max_retry_attempts: usize) -> Self {
+        self.max_retry_attempts = max_retry_attempts;
+        self
+    }
+
+    pub fn with_base_timeout(mut self, base_timeout: std::time::Duration) -> Self {
+        self.base_timeout = base_timeout;
+        self
+    }
+
+    // upper bound on concurrent part/range requests for multipart upload,
+    // ranged download, and prefix transfers; multipart upload and ranged
+    // download only engage when it is > 1, otherwise a single whole-object
+    // request is used, and prefix transfers move one object at a time
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency;
+        self
+    }
+
+    // object size, in bytes, above which downloads switch to concurrent
+    // byte-range GETs
+    pub fn with_br_threshold(mut self, br_threshold: usize) -> Self {
+        self.br_threshold = br_threshold;
+        self
+    }
+
+    // object size, in bytes, above which uploads switch to multipart upload
+    pub fn with_mp_threshold(mut self, mp_threshold: usize) -> Self {
+        self.mp_threshold = mp_threshold;
+        self
+    }
+
+    // size of each part sent by multipart upload
+    pub fn with_mp_chunk_size(mut self, mp_chunk_size: usize) -> Self {
+        self.mp_chunk_size = mp_chunk_size;
+        self
+    }
+
+    pub(crate) fn max_retry_attempts(&self) -> usize {
+        self.max_retry_attempts
+    }
+
+    pub(crate) fn base_timeout(&self) -> std::time::Duration {
+        self.base_timeout
+    }
+
     // for download:
     //   Some - byte-range size
     //   None - for not using byte-range get
@@ -44,6 +100,10 @@ impl S3TransferConfig {
         }
         None
     }
+
+    pub(crate) fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
 }
 
 pub struct S3TransferManager {
@@ -52,6 +112,9 @@ pub struct S3TransferManager {
     set_progress_length: Option<Box<dyn Fn(usize)>>,
     progress_callback: Option<Box<dyn Fn(usize)>>,
     progress_finished: Option<Box<dyn Fn()>>,
+    decompression: Option<Codec>,
+    object_finished: Option<Box<dyn Fn(&str)>>,
+    checksum_validation: bool,
 }
 
 impl S3TransferManager {
@@ -68,6 +131,9 @@ impl S3TransferManager {
             set_progress_length: None,
             progress_callback: None,
             progress_finished: None,
+            decompression: None,
+            object_finished: None,
+            checksum_validation: true,
         }
     }
 
@@ -92,6 +158,28 @@ impl S3TransferManager {
         self
     }
 
+    // forces on-the-fly decompression of downloaded objects with the given
+    // codec; when not set, the codec is auto-detected from the key suffix
+    pub fn with_decompression(mut self, codec: Codec) -> Self {
+        self.decompression = Some(codec);
+        self
+    }
+
+    // called with the key of each object as it finishes transferring during
+    // download_prefix/upload_prefix
+    pub fn with_object_finished(mut self, object_finished: impl Fn(&str) + 'static) -> Self {
+        self.object_finished = Some(Box::new(object_finished));
+        self
+    }
+
+    // enabled by default: validates uploads with a content MD5 and validates
+    // downloads against the object's ETag. Downloads whose ETag carries a
+    // multipart `-N` suffix are not compared, since it isn't a plain MD5
+    pub fn with_checksum_validation(mut self, checksum_validation: bool) -> Self {
+        self.checksum_validation = checksum_validation;
+        self
+    }
+
     pub fn with_update_progress(mut self,
             set_progress_length: impl Fn(usize) + 'static,
             progress_callback: impl Fn(usize) + 'static,
@@ -131,75 +219,1124 @@ impl S3TransferManager {
         }
     }
 
-    pub async fn download(&self, s3uri: &str, buf: &mut Vec<u8>) -> Result<(), Error> {
+    // generic fallback for operations whose service errors don't need
+    // special-casing beyond logging and surfacing to the caller
+    fn error_handler_generic<E, R>(&self, sdk_err: aws_sdk_s3::error::SdkError<E, R>) -> Error
+    where
+        E: std::error::Error + Send + Sync + 'static,
+        R: std::fmt::Debug + Send + Sync + 'static,
+    {
+        trace!(" - {}", sdk_err);
+        Error::new(ErrorKind::Other, sdk_err)
+    }
+
+    fn is_retryable<E, R>(sdk_err: &aws_sdk_s3::error::SdkError<E, R>) -> bool
+    where
+        E: aws_sdk_s3::error::ProvideErrorMetadata,
+    {
+        use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+        match sdk_err {
+            SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) | SdkError::ResponseError(_) => true,
+            SdkError::ServiceError(_) => matches!(
+                sdk_err.code(),
+                Some("SlowDown") | Some("RequestTimeout") | Some("InternalError")
+                    | Some("ServiceUnavailable") | Some("ThrottlingException")
+            ),
+            _ => false,
+        }
+    }
+
+    fn content_md5(data: &[u8]) -> String {
+        base64::encode(md5::compute(data).0)
+    }
+
+    // a multipart ETag looks like "<hex>-<part count>" and isn't a plain MD5
+    // of the object body, so it can't be compared against a streamed digest
+    fn etag_is_simple_md5(etag: &str) -> bool {
+        !etag.trim_matches('"').contains('-')
+    }
+
+    fn verify_checksum(etag: &str, digest: md5::Digest) -> Result<(), Error> {
+        let expected = etag.trim_matches('"');
+        let actual = format!("{:x}", digest);
+        if expected.eq_ignore_ascii_case(&actual) {
+            Ok(())
+        } else {
+            return Err(Error::new(ErrorKind::InvalidData,
+                format!("checksum mismatch: expected {}, got {}", expected, actual)));
+        }
+    }
+
+    // exponential delay for `attempt`, plus up to 50% jitter; split out from
+    // `backoff` so the bounds can be checked without actually sleeping
+    fn backoff_delay_bounds(attempt: u32) -> (std::time::Duration, std::time::Duration) {
+        const BASE_BACKOFF: std::time::Duration = std::time::Duration::from_millis(100);
+        let exp = BASE_BACKOFF.saturating_mul(1u32 << attempt.min(10));
+        let max_jitter = std::time::Duration::from_millis(exp.as_millis() as u64 / 2);
+        (exp, exp + max_jitter)
+    }
+
+    async fn backoff(attempt: u32) {
+        let (min, max) = Self::backoff_delay_bounds(attempt);
+        let jitter = std::time::Duration::from_millis(rand::random::<u64>() % ((max - min).as_millis() as u64 + 1));
+        tokio::time::sleep(min + jitter).await;
+    }
+
+    // retries the S3 call produced by `op` on timeout, dispatch or response
+    // failure, or a retryable service error, with exponential backoff and jitter between attempts; the request
+    // itself is bounded by the configured per-attempt timeout. Without a
+    // config, retry/timeout is a no-op so unconfigured callers keep the
+    // original unbounded behavior
+    async fn with_retry<T, E, R, F, Fut>(&self, mut op: F) -> Result<T, aws_sdk_s3::error::SdkError<E, R>>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, aws_sdk_s3::error::SdkError<E, R>>>,
+        E: aws_sdk_s3::error::ProvideErrorMetadata,
+    {
+        let Some(config) = self.config.as_ref() else {
+            return op().await;
+        };
+        let max_attempts = config.max_retry_attempts();
+        let request_timeout = config.base_timeout();
+
+        let mut attempt = 0;
+        loop {
+            let outcome = match tokio::time::timeout(request_timeout, op()).await {
+                Ok(outcome) => outcome,
+                Err(elapsed) => Err(aws_sdk_s3::error::SdkError::timeout_error(elapsed)),
+            };
+
+            match outcome {
+                Ok(value) => return Ok(value),
+                Err(sdk_err) => {
+                    if attempt < max_attempts && Self::is_retryable(&sdk_err) {
+                        trace!(" - retrying after error: {} (attempt {})", sdk_err, attempt + 1);
+                        Self::backoff(attempt).await;
+                        attempt += 1;
+                        continue;
+                    }
+                    return Err(sdk_err);
+                },
+            }
+        }
+    }
+
+    // `with_retry` only covers the request itself; a streamed GET body can
+    // still fail mid-transfer (e.g. the connection dropping) after the
+    // request already succeeded, which needs a fresh request to recover
+    // from. Callers loop on this to decide whether such a body-read failure
+    // is worth retrying
+    fn body_read_retryable(&self, attempt: usize) -> bool {
+        attempt < self.config.as_ref().map(|c| c.max_retry_attempts()).unwrap_or(0)
+    }
+
+    pub async fn upload(&self, buf: &[u8], s3uri: &str) -> Result<(), Error> {
+        let size = buf.len();
+        let chunk_size = self.config.as_ref().and_then(|c| c.get_upload_chunk_size(size));
+        match chunk_size {
+            Some(chunk_size) => self.multipart_upload_bytes(buf, s3uri, chunk_size).await,
+            None => self.put_object(buf.to_vec(), s3uri).await,
+        }
+    }
+
+    pub async fn upload_file(&self, path: impl AsRef<Path>, s3uri: &str) -> Result<(), Error> {
+        let path = path.as_ref();
+        let size = tokio::fs::metadata(path).await?.len() as usize;
+        let chunk_size = self.config.as_ref().and_then(|c| c.get_upload_chunk_size(size));
+        match chunk_size {
+            Some(chunk_size) => self.multipart_upload_file(path, s3uri, size, chunk_size).await,
+            None => {
+                let data = tokio::fs::read(path).await?;
+                self.put_object(data, s3uri).await
+            },
+        }
+    }
+
+    async fn put_object(&self, data: Vec<u8>, s3uri: &str) -> Result<(), Error> {
         let uri = S3Uri::parse(s3uri).expect("valid S3 Uri");
-        match self.client.get_object()
-                    .bucket(uri.bucket)
-                    .key(uri.key)
-                    .send()
+        let size = data.len();
+        let content_md5 = self.checksum_validation.then(|| Self::content_md5(&data));
+        if let Some(set_progress_length) = &self.set_progress_length {
+            (set_progress_length)(size);
+        }
+        self.with_retry(|| self.client.put_object()
+                .bucket(uri.bucket)
+                .key(uri.key)
+                .set_content_md5(content_md5.clone())
+                .body(ByteStream::from(data.clone()))
+                .send())
+            .await
+            .map_err(|e| self.error_handler_generic(e))?;
+        if let Some(progress_callback) = &self.progress_callback {
+            (progress_callback)(size);
+        }
+        if let Some(finish) = &self.progress_finished {
+            (finish)();
+        }
+        Ok(())
+    }
+
+    async fn upload_one_part(&self, uri: &S3Uri<'_>, upload_id: &str, part_number: i32, data: Vec<u8>) -> Result<CompletedPart, Error> {
+        let len = data.len();
+        let content_md5 = self.checksum_validation.then(|| Self::content_md5(&data));
+        let resp = self.with_retry(|| self.client.upload_part()
+                .bucket(uri.bucket)
+                .key(uri.key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .set_content_md5(content_md5.clone())
+                .body(ByteStream::from(data.clone()))
+                .send())
+            .await
+            .map_err(|e| self.error_handler_generic(e))?;
+        if let Some(progress_callback) = &self.progress_callback {
+            (progress_callback)(len);
+        }
+        Ok(CompletedPart::builder()
+            .part_number(part_number)
+            .set_e_tag(resp.e_tag)
+            .build())
+    }
+
+    async fn complete_or_abort(&self, uri: &S3Uri<'_>, upload_id: &str, parts: Result<Vec<CompletedPart>, Error>) -> Result<(), Error> {
+        match parts {
+            Ok(mut parts) => {
+                parts.sort_by_key(|p| p.part_number());
+                let completed = CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build();
+                self.with_retry(|| self.client.complete_multipart_upload()
+                        .bucket(uri.bucket)
+                        .key(uri.key)
+                        .upload_id(upload_id)
+                        .multipart_upload(completed.clone())
+                        .send())
                     .await
-        {
-            Ok(output) => {
-                // set total length if we have set function
-                if let Some(set) = &self.set_progress_length {
-                    (set)(output.content_length.map(|x| x as usize).unwrap_or(0));
-                }
-                let stream = ByteStreamProgress::new(
-                    output.body,
-                    self.progress_callback
-                        .as_ref()
-                        .map(|cb| cb.as_ref())
-                );
-                let mut reader = stream.into_async_read();
-                reader.read_to_end(buf).await?;
+                    .map_err(|e| self.error_handler_generic(e))?;
                 if let Some(finish) = &self.progress_finished {
                     (finish)();
                 }
-                return Ok(());
+                Ok(())
+            },
+            Err(e) => {
+                trace!(" - aborting multipart upload {}", upload_id);
+                let _ = self.with_retry(|| self.client.abort_multipart_upload()
+                        .bucket(uri.bucket)
+                        .key(uri.key)
+                        .upload_id(upload_id)
+                        .send())
+                    .await;
+                Err(e)
             },
-            Err(sdk_err) => {
-                return self.error_handler_get_object(sdk_err);
+        }
+    }
+
+    async fn multipart_upload_bytes(&self, buf: &[u8], s3uri: &str, chunk_size: usize) -> Result<(), Error> {
+        let uri = S3Uri::parse(s3uri).expect("valid S3 Uri");
+        let size = buf.len();
+        let create = self.with_retry(|| self.client.create_multipart_upload()
+                .bucket(uri.bucket)
+                .key(uri.key)
+                .send())
+            .await
+            .map_err(|e| self.error_handler_generic(e))?;
+        let upload_id = create.upload_id.expect("upload id from create_multipart_upload");
+
+        if let Some(set_progress_length) = &self.set_progress_length {
+            (set_progress_length)(size);
+        }
+
+        let num_parts = (size + chunk_size - 1) / chunk_size;
+        let max_concurrency = self.config.as_ref().map(|c| c.max_concurrency()).unwrap_or(1);
+
+        let parts: Result<Vec<CompletedPart>, Error> = stream::iter(0..num_parts)
+            .map(|i| {
+                let offset = i * chunk_size;
+                let len = chunk_size.min(size - offset);
+                let part_number = (i + 1) as i32;
+                let data = buf[offset..offset + len].to_vec();
+                async move {
+                    self.upload_one_part(&uri, &upload_id, part_number, data).await
+                }
+            })
+            .buffer_unordered(max_concurrency)
+            .collect::<Vec<Result<CompletedPart, Error>>>()
+            .await
+            .into_iter()
+            .collect();
+
+        self.complete_or_abort(&uri, &upload_id, parts).await
+    }
+
+    async fn multipart_upload_file(&self, path: &Path, s3uri: &str, size: usize, chunk_size: usize) -> Result<(), Error> {
+        let uri = S3Uri::parse(s3uri).expect("valid S3 Uri");
+        let create = self.with_retry(|| self.client.create_multipart_upload()
+                .bucket(uri.bucket)
+                .key(uri.key)
+                .send())
+            .await
+            .map_err(|e| self.error_handler_generic(e))?;
+        let upload_id = create.upload_id.expect("upload id from create_multipart_upload");
+
+        if let Some(set_progress_length) = &self.set_progress_length {
+            (set_progress_length)(size);
+        }
+
+        let num_parts = (size + chunk_size - 1) / chunk_size;
+        let max_concurrency = self.config.as_ref().map(|c| c.max_concurrency()).unwrap_or(1);
+
+        let parts: Result<Vec<CompletedPart>, Error> = stream::iter(0..num_parts)
+            .map(|i| {
+                let offset = i * chunk_size;
+                let len = chunk_size.min(size - offset);
+                let part_number = (i + 1) as i32;
+                async move {
+                    let mut file = tokio::fs::File::open(path).await?;
+                    file.seek(std::io::SeekFrom::Start(offset as u64)).await?;
+                    let mut data = vec![0u8; len];
+                    file.read_exact(&mut data).await?;
+                    self.upload_one_part(&uri, &upload_id, part_number, data).await
+                }
+            })
+            .buffer_unordered(max_concurrency)
+            .collect::<Vec<Result<CompletedPart, Error>>>()
+            .await
+            .into_iter()
+            .collect();
+
+        self.complete_or_abort(&uri, &upload_id, parts).await
+    }
+
+    async fn head_object_meta(&self, uri: &S3Uri<'_>) -> Result<(Option<String>, usize), Error> {
+        let output = self.with_retry(|| self.client.head_object()
+                .bucket(uri.bucket)
+                .key(uri.key)
+                .send())
+            .await
+            .map_err(|e| self.error_handler_generic(e))?;
+        Ok((output.e_tag, output.content_length.map(|x| x as usize).unwrap_or(0)))
+    }
+
+    // re-reads a just-written file to compute its MD5, used to checksum
+    // ranged downloads where chunks arrive out of order and can't be hashed
+    // incrementally as they stream in
+    async fn hash_file(path: &Path) -> Result<md5::Digest, Error> {
+        let mut file = tokio::fs::File::open(path).await?;
+        let mut ctx = md5::Context::new();
+        let mut buf = vec![0u8; 65536];
+        loop {
+            let n = file.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            ctx.consume(&buf[..n]);
+        }
+        Ok(ctx.compute())
+    }
+
+    async fn fetch_range(&self, uri: &S3Uri<'_>, start: u64, end: u64) -> Result<Vec<u8>, Error> {
+        let mut attempt = 0;
+        loop {
+            let output = self.with_retry(|| self.client.get_object()
+                    .bucket(uri.bucket)
+                    .key(uri.key)
+                    .range(format!("bytes={}-{}", start, end))
+                    .send())
+                .await
+                .map_err(|e| self.error_handler_generic(e))?;
+            let stream = ByteStreamProgress::new(
+                output.body,
+                self.progress_callback
+                    .as_ref()
+                    .map(|cb| cb.as_ref())
+            );
+            let mut reader = stream.into_async_read();
+            let mut data = Vec::new();
+            match reader.read_to_end(&mut data).await {
+                Ok(_) => return Ok(data),
+                Err(err) if self.body_read_retryable(attempt) => {
+                    trace!(" - retrying range fetch after body read error: {} (attempt {})", err, attempt + 1);
+                    Self::backoff(attempt as u32).await;
+                    attempt += 1;
+                },
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    async fn ranged_download(&self, uri: &S3Uri<'_>, etag: Option<&str>, size: usize, chunk_size: usize, buf: &mut Vec<u8>) -> Result<(), Error> {
+        if let Some(set_progress_length) = &self.set_progress_length {
+            (set_progress_length)(size);
+        }
+
+        let max_concurrency = self.config.as_ref().map(|c| c.max_concurrency()).unwrap_or(1);
+        let num_ranges = (size + chunk_size - 1) / chunk_size;
+
+        let ranges: Result<Vec<(usize, Vec<u8>)>, Error> = stream::iter(0..num_ranges)
+            .map(|i| {
+                let start = i * chunk_size;
+                let end = (start + chunk_size).min(size) - 1;
+                async move {
+                    let data = self.fetch_range(uri, start as u64, end as u64).await?;
+                    Ok((start, data))
+                }
+            })
+            .buffer_unordered(max_concurrency)
+            .collect::<Vec<Result<(usize, Vec<u8>), Error>>>()
+            .await
+            .into_iter()
+            .collect();
+
+        buf.clear();
+        buf.resize(size, 0);
+        for (offset, data) in ranges? {
+            buf[offset..offset + data.len()].copy_from_slice(&data);
+        }
+
+        if self.checksum_validation && etag.is_some_and(Self::etag_is_simple_md5) {
+            let digest = md5::compute(&buf);
+            Self::verify_checksum(etag.unwrap_or(""), digest)?;
+        }
+
+        if let Some(finish) = &self.progress_finished {
+            (finish)();
+        }
+        Ok(())
+    }
+
+    async fn ranged_download_file(&self, uri: &S3Uri<'_>, etag: Option<&str>, size: usize, chunk_size: usize, path: &Path) -> Result<(), Error> {
+        if let Some(set_progress_length) = &self.set_progress_length {
+            (set_progress_length)(size);
+        }
+
+        let file = tokio::fs::File::create(path).await?;
+        file.set_len(size as u64).await?;
+        drop(file);
+
+        let max_concurrency = self.config.as_ref().map(|c| c.max_concurrency()).unwrap_or(1);
+        let num_ranges = (size + chunk_size - 1) / chunk_size;
+
+        let result: Result<Vec<()>, Error> = stream::iter(0..num_ranges)
+            .map(|i| {
+                let start = i * chunk_size;
+                let end = (start + chunk_size).min(size) - 1;
+                async move {
+                    let data = self.fetch_range(uri, start as u64, end as u64).await?;
+                    let mut file = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+                    file.seek(std::io::SeekFrom::Start(start as u64)).await?;
+                    file.write_all(&data).await?;
+                    Ok(())
+                }
+            })
+            .buffer_unordered(max_concurrency)
+            .collect::<Vec<Result<(), Error>>>()
+            .await
+            .into_iter()
+            .collect();
+        result?;
+
+        if self.checksum_validation && etag.is_some_and(Self::etag_is_simple_md5) {
+            let digest = Self::hash_file(path).await?;
+            Self::verify_checksum(etag.unwrap_or(""), digest)?;
+        }
+
+        if let Some(finish) = &self.progress_finished {
+            (finish)();
+        }
+        Ok(())
+    }
+
+    pub async fn download(&self, s3uri: &str, buf: &mut Vec<u8>) -> Result<(), Error> {
+        let uri = S3Uri::parse(s3uri).expect("valid S3 Uri");
+        let codec = self.decompression.or_else(|| Codec::from_key(uri.key));
+
+        // ranged GETs fetch chunks independently and out of order, which isn't
+        // compatible with decoding a single contiguous compressed stream, so
+        // fall through to the single-shot path whenever a codec applies
+        if codec.is_none() {
+            if let Some(config) = &self.config {
+                let (etag, size) = self.head_object_meta(&uri).await?;
+                if let Some(chunk_size) = config.get_download_chunk_size(size) {
+                    return self.ranged_download(&uri, etag.as_deref(), size, chunk_size, buf).await;
+                }
+            }
+        }
+
+        let mut attempt = 0;
+        loop {
+            match self.with_retry(|| self.client.get_object()
+                        .bucket(uri.bucket)
+                        .key(uri.key)
+                        .send())
+                        .await
+            {
+                Ok(output) => {
+                    // set total length if we have set function
+                    if let Some(set) = &self.set_progress_length {
+                        (set)(output.content_length.map(|x| x as usize).unwrap_or(0));
+                    }
+                    let etag = output.e_tag.clone();
+                    let checksum = self.checksum_validation
+                        && etag.as_deref().is_some_and(Self::etag_is_simple_md5);
+                    let checksum = checksum.then(|| Rc::new(RefCell::new(md5::Context::new())));
+                    let mut stream = ByteStreamProgress::new(
+                        output.body,
+                        self.progress_callback
+                            .as_ref()
+                            .map(|cb| cb.as_ref())
+                    );
+                    if let Some(checksum) = &checksum {
+                        stream = stream.with_checksum(checksum.clone());
+                    }
+                    let reader = stream.into_async_read();
+                    buf.clear();
+                    let read_result = match codec {
+                        Some(codec) => {
+                            let mut decoded = codec::decode(codec, reader);
+                            decoded.read_to_end(buf).await
+                        },
+                        None => {
+                            let mut reader = reader;
+                            reader.read_to_end(buf).await
+                        },
+                    };
+                    if let Err(err) = read_result {
+                        if self.body_read_retryable(attempt) {
+                            trace!(" - retrying download after body read error: {} (attempt {})", err, attempt + 1);
+                            Self::backoff(attempt as u32).await;
+                            attempt += 1;
+                            continue;
+                        }
+                        return Err(err);
+                    }
+                    if let Some(checksum) = checksum {
+                        let digest = checksum.borrow().clone().compute();
+                        Self::verify_checksum(etag.as_deref().unwrap_or(""), digest)?;
+                    }
+                    if let Some(finish) = &self.progress_finished {
+                        (finish)();
+                    }
+                    return Ok(());
+                },
+                Err(sdk_err) => {
+                    return self.error_handler_get_object(sdk_err);
+                }
             }
         }
     }
 
     pub async fn download_file(&self, s3uri: &str, path: impl AsRef<Path>) -> Result<(), Error> {
         let uri = S3Uri::parse(s3uri).expect("valid S3 Uri");
-        match self.client.get_object()
+        let path = path.as_ref();
+        let codec = self.decompression.or_else(|| Codec::from_key(uri.key));
+
+        // ranged GETs fetch chunks independently and out of order, which isn't
+        // compatible with decoding a single contiguous compressed stream, so
+        // fall through to the single-shot path whenever a codec applies
+        if codec.is_none() {
+            if let Some(config) = &self.config {
+                let (etag, size) = self.head_object_meta(&uri).await?;
+                if let Some(chunk_size) = config.get_download_chunk_size(size) {
+                    return self.ranged_download_file(&uri, etag.as_deref(), size, chunk_size, path).await;
+                }
+            }
+        }
+
+        let mut attempt = 0;
+        loop {
+            match self.with_retry(|| self.client.get_object()
+                        .bucket(uri.bucket)
+                        .key(uri.key)
+                        .send())
+                        .await
+            {
+                Ok(output) => {
+                    // set total length if we have set function
+                    if let Some(set_progress_length) = &self.set_progress_length {
+                        (set_progress_length)(output.content_length.map(|x| x as usize).unwrap_or(0));
+                    }
+                    let etag = output.e_tag.clone();
+                    let checksum = self.checksum_validation
+                        && etag.as_deref().is_some_and(Self::etag_is_simple_md5);
+                    let checksum = checksum.then(|| Rc::new(RefCell::new(md5::Context::new())));
+                    let mut stream = ByteStreamProgress::new(
+                        output.body,
+                        self.progress_callback
+                            .as_ref()
+                            .map(|cb| cb.as_ref())
+                    );
+                    if let Some(checksum) = &checksum {
+                        stream = stream.with_checksum(checksum.clone());
+                    }
+                    let reader = stream.into_async_read();
+                    let file = tokio::fs::File::create(path).await?;
+                    let mut writer = tokio::io::BufWriter::new(file);
+                    let copy_result = match codec {
+                        Some(codec) => {
+                            let mut decoded = codec::decode(codec, reader);
+                            tokio::io::copy_buf(&mut decoded, &mut writer).await
+                        },
+                        None => {
+                            let mut reader = reader;
+                            tokio::io::copy_buf(&mut reader, &mut writer).await
+                        },
+                    };
+                    if let Err(err) = copy_result {
+                        if self.body_read_retryable(attempt) {
+                            trace!(" - retrying download after body read error: {} (attempt {})", err, attempt + 1);
+                            Self::backoff(attempt as u32).await;
+                            attempt += 1;
+                            continue;
+                        }
+                        return Err(err);
+                    }
+                    if let Some(checksum) = checksum {
+                        let digest = checksum.borrow().clone().compute();
+                        Self::verify_checksum(etag.as_deref().unwrap_or(""), digest)?;
+                    }
+                    if let Some(finish) = &self.progress_finished {
+                        (finish)();
+                    }
+                    return Ok(());
+                },
+                Err(sdk_err) => {
+                    return self.error_handler_get_object(sdk_err);
+                }
+            }
+        }
+    }
+
+    fn sidecar_path(path: &Path) -> std::path::PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".s3part");
+        std::path::PathBuf::from(name)
+    }
+
+    async fn write_sidecar(sidecar: &Path, etag: &str, size: usize) -> Result<(), Error> {
+        let mut tmp_name = sidecar.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp = std::path::PathBuf::from(tmp_name);
+        tokio::fs::write(&tmp, format!("{}\n{}\n", etag, size)).await?;
+        tokio::fs::rename(&tmp, sidecar).await?;
+        Ok(())
+    }
+
+    async fn sidecar_matches(sidecar: &Path, etag: &str, size: usize) -> bool {
+        let contents = match tokio::fs::read_to_string(sidecar).await {
+            Ok(contents) => contents,
+            Err(_) => return false,
+        };
+        let mut lines = contents.lines();
+        let stored_etag = lines.next().unwrap_or("");
+        let stored_size: usize = lines.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+        stored_etag == etag && stored_size == size
+    }
+
+    fn is_precondition_failed<E, R>(sdk_err: &aws_sdk_s3::error::SdkError<E, R>) -> bool
+    where
+        E: aws_sdk_s3::error::ProvideErrorMetadata,
+    {
+        use aws_sdk_s3::error::ProvideErrorMetadata;
+        sdk_err.code() == Some("PreconditionFailed")
+    }
+
+    // resumes a partially downloaded file, validating that the object hasn't
+    // changed on the server since the partial download started
+    pub async fn resume_download_file(&self, s3uri: &str, path: impl AsRef<Path>) -> Result<(), Error> {
+        let uri = S3Uri::parse(s3uri).expect("valid S3 Uri");
+        let path = path.as_ref();
+        let sidecar = Self::sidecar_path(path);
+
+        let head = self.with_retry(|| self.client.head_object()
+                .bucket(uri.bucket)
+                .key(uri.key)
+                .send())
+            .await
+            .map_err(|e| self.error_handler_generic(e))?;
+        let mut etag = head.e_tag.clone().unwrap_or_default();
+        let mut size = head.content_length.map(|x| x as usize).unwrap_or(0);
+
+        if let Some(set_progress_length) = &self.set_progress_length {
+            (set_progress_length)(size);
+        }
+
+        let metadata = tokio::fs::metadata(path).await.ok();
+        let matches_sidecar = Self::sidecar_matches(&sidecar, &etag, size).await;
+        let mut offset = match metadata {
+            Some(m) if matches_sidecar => m.len(),
+            _ => 0,
+        };
+
+        if offset > 0 {
+            if let Some(progress_callback) = &self.progress_callback {
+                (progress_callback)(offset as usize);
+            }
+        }
+
+        Self::write_sidecar(&sidecar, &etag, size).await?;
+
+        let mut body_attempt = 0;
+        loop {
+            let result = self.with_retry(|| self.client.get_object()
                     .bucket(uri.bucket)
                     .key(uri.key)
-                    .send()
-                    .await
-        {
-            Ok(output) => {
-                // set total length if we have set function
-                if let Some(set_progress_length) = &self.set_progress_length {
-                    (set_progress_length)(output.content_length.map(|x| x as usize).unwrap_or(0));
+                    .range(format!("bytes={}-", offset))
+                    .if_match(&etag)
+                    .send())
+                .await;
+
+            match result {
+                Ok(output) => {
+                    let stream = ByteStreamProgress::new(
+                        output.body,
+                        self.progress_callback
+                            .as_ref()
+                            .map(|cb| cb.as_ref())
+                    );
+                    let mut reader = stream.into_async_read();
+                    let file = tokio::fs::OpenOptions::new()
+                        .create(true)
+                        .write(true)
+                        .truncate(offset == 0)
+                        .open(path)
+                        .await?;
+                    let mut file = file;
+                    file.seek(std::io::SeekFrom::Start(offset)).await?;
+                    let mut writer = tokio::io::BufWriter::new(file);
+                    if let Err(err) = tokio::io::copy_buf(&mut reader, &mut writer).await {
+                        if self.body_read_retryable(body_attempt) {
+                            trace!(" - retrying resumed download after body read error: {} (attempt {})", err, body_attempt + 1);
+                            // drop any bytes the failed attempt wrote past the
+                            // last known-good offset before re-requesting it
+                            writer.get_ref().set_len(offset).await?;
+                            Self::backoff(body_attempt as u32).await;
+                            body_attempt += 1;
+                            continue;
+                        }
+                        return Err(err);
+                    }
+                    break;
+                },
+                Err(sdk_err) if Self::is_precondition_failed(&sdk_err) => {
+                    trace!(" - object changed underneath us, restarting download from byte 0");
+                    tokio::fs::remove_file(path).await.ok();
+                    let head = self.with_retry(|| self.client.head_object()
+                            .bucket(uri.bucket)
+                            .key(uri.key)
+                            .send())
+                        .await
+                        .map_err(|e| self.error_handler_generic(e))?;
+                    etag = head.e_tag.clone().unwrap_or_default();
+                    size = head.content_length.map(|x| x as usize).unwrap_or(0);
+                    if let Some(set_progress_length) = &self.set_progress_length {
+                        (set_progress_length)(size);
+                    }
+                    Self::write_sidecar(&sidecar, &etag, size).await?;
+                    offset = 0;
+                    continue;
+                },
+                Err(sdk_err) => {
+                    return self.error_handler_get_object(sdk_err);
+                },
+            }
+        }
+
+        // validate against the whole file rather than just the resumed tail,
+        // since the already-present bytes were never hashed as they streamed in
+        if self.checksum_validation && Self::etag_is_simple_md5(&etag) {
+            let digest = Self::hash_file(path).await?;
+            Self::verify_checksum(&etag, digest)?;
+        }
+
+        if let Some(finish) = &self.progress_finished {
+            (finish)();
+        }
+        tokio::fs::remove_file(&sidecar).await.ok();
+        Ok(())
+    }
+
+    // pulls the (key, size) pairs out of one list_objects_v2 page
+    fn page_keys(contents: Option<Vec<aws_sdk_s3::types::Object>>) -> Vec<(String, usize)> {
+        let mut keys = Vec::new();
+        for object in contents.unwrap_or_default() {
+            if let Some(key) = object.key {
+                let size = object.size.map(|x| x as usize).unwrap_or(0);
+                keys.push((key, size));
+            }
+        }
+        keys
+    }
+
+    // decides whether list_objects_v2 pagination should continue, and with
+    // which token
+    fn next_continuation_token(is_truncated: Option<bool>, next_token: Option<String>) -> Option<String> {
+        if is_truncated.unwrap_or(false) {
+            next_token
+        } else {
+            None
+        }
+    }
+
+    async fn list_all_keys(&self, bucket: &str, prefix: &str) -> Result<Vec<(String, usize)>, Error> {
+        let mut keys = Vec::new();
+        let mut continuation_token: Option<String> = None;
+        loop {
+            let mut request = self.client.list_objects_v2()
+                .bucket(bucket)
+                .prefix(prefix);
+            if let Some(token) = &continuation_token {
+                request = request.continuation_token(token);
+            }
+            let output = self.with_retry(|| request.clone().send())
+                .await
+                .map_err(|e| self.error_handler_generic(e))?;
+            keys.extend(Self::page_keys(output.contents));
+            match Self::next_continuation_token(output.is_truncated, output.next_continuation_token) {
+                Some(token) => continuation_token = Some(token),
+                None => break,
+            }
+        }
+        Ok(keys)
+    }
+
+    async fn fetch_object_to_file(&self, uri: &S3Uri<'_>, path: &Path) -> Result<(), Error> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut attempt = 0;
+        loop {
+            let output = self.with_retry(|| self.client.get_object()
+                    .bucket(uri.bucket)
+                    .key(uri.key)
+                    .send())
+                .await
+                .map_err(|e| self.error_handler_generic(e))?;
+            let etag = output.e_tag.clone();
+            let checksum = self.checksum_validation
+                && etag.as_deref().is_some_and(Self::etag_is_simple_md5);
+            let checksum = checksum.then(|| Rc::new(RefCell::new(md5::Context::new())));
+            let mut stream = ByteStreamProgress::new(
+                output.body,
+                self.progress_callback
+                    .as_ref()
+                    .map(|cb| cb.as_ref())
+            );
+            if let Some(checksum) = &checksum {
+                stream = stream.with_checksum(checksum.clone());
+            }
+            let mut reader = stream.into_async_read();
+            let file = tokio::fs::File::create(path).await?;
+            let mut writer = tokio::io::BufWriter::new(file);
+            if let Err(err) = tokio::io::copy_buf(&mut reader, &mut writer).await {
+                if self.body_read_retryable(attempt) {
+                    trace!(" - retrying fetch after body read error: {} (attempt {})", err, attempt + 1);
+                    Self::backoff(attempt as u32).await;
+                    attempt += 1;
+                    continue;
                 }
-                let stream = ByteStreamProgress::new(
-                    output.body,
-                    self.progress_callback
-                        .as_ref()
-                        .map(|cb| cb.as_ref())
-                );
-                let mut reader = stream.into_async_read();
-                let file = tokio::fs::File::create(path).await?;
-                let mut writer = tokio::io::BufWriter::new(file);
-                tokio::io::copy_buf(&mut reader, &mut writer).await?;
-                if let Some(finish) = &self.progress_finished {
-                    (finish)();
+                return Err(err);
+            }
+            if let Some(checksum) = checksum {
+                let digest = checksum.borrow().clone().compute();
+                Self::verify_checksum(etag.as_deref().unwrap_or(""), digest)?;
+            }
+            break;
+        }
+        Ok(())
+    }
+
+    async fn put_object_no_progress_length(&self, data: Vec<u8>, uri: &S3Uri<'_>) -> Result<(), Error> {
+        let size = data.len();
+        let content_md5 = self.checksum_validation.then(|| Self::content_md5(&data));
+        self.with_retry(|| self.client.put_object()
+                .bucket(uri.bucket)
+                .key(uri.key)
+                .set_content_md5(content_md5.clone())
+                .body(ByteStream::from(data.clone()))
+                .send())
+            .await
+            .map_err(|e| self.error_handler_generic(e))?;
+        if let Some(progress_callback) = &self.progress_callback {
+            (progress_callback)(size);
+        }
+        Ok(())
+    }
+
+    async fn walk_files(dir: &Path) -> Result<Vec<std::path::PathBuf>, Error> {
+        let mut files = Vec::new();
+        let mut stack = vec![dir.to_path_buf()];
+        while let Some(current) = stack.pop() {
+            let mut entries = tokio::fs::read_dir(&current).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let file_type = entry.file_type().await?;
+                if file_type.is_dir() {
+                    stack.push(entry.path());
+                } else if file_type.is_file() {
+                    files.push(entry.path());
                 }
-                return Ok(());
-            },
-            Err(sdk_err) => {
-                return self.error_handler_get_object(sdk_err);
             }
         }
+        Ok(files)
+    }
+
+    // strips `prefix` off `key` to get the path relative to the prefix,
+    // matching how `join_prefix_key` joined it on the way up
+    fn key_to_relative_path(prefix: &str, key: &str) -> &str {
+        key.strip_prefix(prefix).unwrap_or(key).trim_start_matches('/')
+    }
+
+    // joins `prefix` and `relative` with a single `/`, unless `prefix` is
+    // empty or already ends in one
+    fn join_prefix_key(prefix: &str, relative: &str) -> String {
+        if prefix.is_empty() || prefix.ends_with('/') {
+            format!("{}{}", prefix, relative)
+        } else {
+            format!("{}/{}", prefix, relative)
+        }
+    }
+
+    // downloads every object under `s3uri_prefix` into `local_dir`, preserving
+    // each key's suffix as the relative path
+    pub async fn download_prefix(&self, s3uri_prefix: &str, local_dir: impl AsRef<Path>) -> Result<(), Error> {
+        let uri = S3Uri::parse(s3uri_prefix).expect("valid S3 Uri");
+        let bucket = uri.bucket;
+        let prefix = uri.key;
+        let local_dir = local_dir.as_ref();
+
+        let objects = self.list_all_keys(bucket, prefix).await?;
+        let total: usize = objects.iter().map(|(_, size)| size).sum();
+
+        if let Some(set_progress_length) = &self.set_progress_length {
+            (set_progress_length)(total);
+        }
+
+        let max_concurrency = self.config.as_ref().map(|c| c.max_concurrency()).unwrap_or(1);
+
+        let results: Vec<Result<(), Error>> = stream::iter(objects)
+            .map(|(key, _size)| {
+                let relative = Self::key_to_relative_path(prefix, &key);
+                let path = local_dir.join(relative);
+                async move {
+                    let obj_uri = S3Uri { bucket, key: &key };
+                    let result = self.fetch_object_to_file(&obj_uri, &path).await;
+                    if result.is_ok() {
+                        if let Some(cb) = &self.object_finished {
+                            (cb)(&key);
+                        }
+                    }
+                    result
+                }
+            })
+            .buffer_unordered(max_concurrency)
+            .collect::<Vec<Result<(), Error>>>()
+            .await;
+
+        if let Some(finish) = &self.progress_finished {
+            (finish)();
+        }
+
+        results.into_iter().collect()
+    }
+
+    // uploads every file under `local_dir`, mapping each to `s3uri_prefix` +
+    // its path relative to `local_dir`
+    pub async fn upload_prefix(&self, local_dir: impl AsRef<Path>, s3uri_prefix: &str) -> Result<(), Error> {
+        let uri = S3Uri::parse(s3uri_prefix).expect("valid S3 Uri");
+        let bucket = uri.bucket;
+        let prefix = uri.key;
+        let local_dir = local_dir.as_ref();
+
+        let files = Self::walk_files(local_dir).await?;
+
+        let mut total = 0usize;
+        let mut sized_files = Vec::with_capacity(files.len());
+        for path in files {
+            let size = tokio::fs::metadata(&path).await?.len() as usize;
+            total += size;
+            sized_files.push((path, size));
+        }
+
+        if let Some(set_progress_length) = &self.set_progress_length {
+            (set_progress_length)(total);
+        }
+
+        let max_concurrency = self.config.as_ref().map(|c| c.max_concurrency()).unwrap_or(1);
+
+        let results: Vec<Result<(), Error>> = stream::iter(sized_files)
+            .map(|(path, _size)| {
+                let relative = path.strip_prefix(local_dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+                let key = Self::join_prefix_key(prefix, &relative);
+                async move {
+                    let data = tokio::fs::read(&path).await?;
+                    let obj_uri = S3Uri { bucket, key: &key };
+                    let result = self.put_object_no_progress_length(data, &obj_uri).await;
+                    if result.is_ok() {
+                        if let Some(cb) = &self.object_finished {
+                            (cb)(&key);
+                        }
+                    }
+                    result
+                }
+            })
+            .buffer_unordered(max_concurrency)
+            .collect::<Vec<Result<(), Error>>>()
+            .await;
+
+        if let Some(finish) = &self.progress_finished {
+            (finish)();
+        }
+
+        results.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_upload_chunk_size_single_threaded() {
+        // with the default (single-threaded) concurrency, multipart upload
+        // never kicks in regardless of object size
+        let config = S3TransferConfig::new();
+        assert_eq!(config.get_upload_chunk_size(0), None);
+        assert_eq!(config.get_upload_chunk_size(usize::MAX), None);
+    }
+
+    #[test]
+    fn test_get_download_chunk_size_single_threaded() {
+        // same gating as multipart upload: no ranged GET without concurrency
+        let config = S3TransferConfig::new();
+        assert_eq!(config.get_download_chunk_size(0), None);
+        assert_eq!(config.get_download_chunk_size(usize::MAX), None);
+    }
+
+    #[test]
+    fn test_config_builders_enable_chunked_transfer() {
+        let config = S3TransferConfig::new()
+            .with_max_concurrency(4)
+            .with_mp_threshold(1_000)
+            .with_mp_chunk_size(100)
+            .with_br_threshold(1_000);
+        assert_eq!(config.get_upload_chunk_size(2_000), Some(100));
+        assert_eq!(config.get_upload_chunk_size(500), None);
+        assert_eq!(config.get_download_chunk_size(2_000), Some(250));
+        assert_eq!(config.get_download_chunk_size(500), None);
+    }
+
+    #[test]
+    fn test_sidecar_path() {
+        let path = Path::new("/tmp/object.bin");
+        assert_eq!(
+            S3TransferManager::sidecar_path(path),
+            std::path::PathBuf::from("/tmp/object.bin.s3part")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_sidecar_and_matches() {
+        let dir = std::env::temp_dir().join(format!("s3tm-sidecar-test-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let sidecar = dir.join("object.bin.s3part");
+
+        assert!(!S3TransferManager::sidecar_matches(&sidecar, "etag", 10).await);
+
+        S3TransferManager::write_sidecar(&sidecar, "etag", 10).await.unwrap();
+        assert!(S3TransferManager::sidecar_matches(&sidecar, "etag", 10).await);
+        assert!(!S3TransferManager::sidecar_matches(&sidecar, "etag", 11).await);
+        assert!(!S3TransferManager::sidecar_matches(&sidecar, "other", 10).await);
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        let timeout: aws_sdk_s3::error::SdkError<GetObjectError, ()> =
+            aws_sdk_s3::error::SdkError::timeout_error("timed out");
+        assert!(S3TransferManager::is_retryable(&timeout));
+
+        let construction: aws_sdk_s3::error::SdkError<GetObjectError, ()> =
+            aws_sdk_s3::error::SdkError::construction_failure("bad request");
+        assert!(!S3TransferManager::is_retryable(&construction));
+    }
+
+    #[test]
+    fn test_backoff_delay_bounds_grows_and_caps() {
+        let (min0, max0) = S3TransferManager::backoff_delay_bounds(0);
+        assert_eq!(min0, std::time::Duration::from_millis(100));
+        assert!(max0 > min0);
+
+        let (min1, _) = S3TransferManager::backoff_delay_bounds(1);
+        assert!(min1 > min0);
+
+        // the shift is clamped so huge attempt counts don't overflow
+        let (min_capped, max_capped) = S3TransferManager::backoff_delay_bounds(10);
+        let (min_over, max_over) = S3TransferManager::backoff_delay_bounds(100);
+        assert_eq!(min_capped, min_over);
+        assert_eq!(max_capped, max_over);
+    }
+
+    #[test]
+    fn test_etag_is_simple_md5() {
+        assert!(S3TransferManager::etag_is_simple_md5("\"9e107d9d372bb6826bd81d3542a419d6\""));
+        assert!(!S3TransferManager::etag_is_simple_md5("\"9e107d9d372bb6826bd81d3542a419d6-3\""));
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let digest = md5::compute(b"hello world");
+        let etag = format!("\"{:x}\"", digest);
+        assert!(S3TransferManager::verify_checksum(&etag, digest).is_ok());
+        assert!(S3TransferManager::verify_checksum("\"deadbeef\"", digest).is_err());
+    }
+
+    #[test]
+    fn test_content_md5() {
+        let a = S3TransferManager::content_md5(b"hello world");
+        let b = S3TransferManager::content_md5(b"hello world");
+        let c = S3TransferManager::content_md5(b"goodbye world");
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_key_to_relative_path() {
+        assert_eq!(S3TransferManager::key_to_relative_path("data", "data/sub/a.txt"), "sub/a.txt");
+        assert_eq!(S3TransferManager::key_to_relative_path("data/", "data/sub/a.txt"), "sub/a.txt");
+        assert_eq!(S3TransferManager::key_to_relative_path("", "data/sub/a.txt"), "data/sub/a.txt");
+    }
+
+    #[test]
+    fn test_join_prefix_key() {
+        assert_eq!(S3TransferManager::join_prefix_key("data", "sub/a.txt"), "data/sub/a.txt");
+        assert_eq!(S3TransferManager::join_prefix_key("data/", "sub/a.txt"), "data/sub/a.txt");
+        assert_eq!(S3TransferManager::join_prefix_key("", "sub/a.txt"), "sub/a.txt");
+    }
+
+    #[test]
+    fn test_prefix_key_mapping_round_trips() {
+        let prefix = "data";
+        let relative = "sub/a.txt";
+        let key = S3TransferManager::join_prefix_key(prefix, relative);
+        assert_eq!(S3TransferManager::key_to_relative_path(prefix, &key), relative);
+    }
+
+    #[test]
+    fn test_page_keys() {
+        let contents = vec![
+            aws_sdk_s3::types::Object::builder().key("a.txt").size(1).build(),
+            aws_sdk_s3::types::Object::builder().key("b.txt").size(2).build(),
+            aws_sdk_s3::types::Object::builder().size(3).build(), // no key, skipped
+        ];
+        let keys = S3TransferManager::page_keys(Some(contents));
+        assert_eq!(keys, vec![("a.txt".to_string(), 1), ("b.txt".to_string(), 2)]);
+        assert_eq!(S3TransferManager::page_keys(None), Vec::<(String, usize)>::new());
     }
 
-    /*
-    pub fn resumeDownloadFile() -> Result<()> {
-        todo!();
+    #[test]
+    fn test_next_continuation_token() {
+        assert_eq!(S3TransferManager::next_continuation_token(Some(true), Some("tok".to_string())), Some("tok".to_string()));
+        assert_eq!(S3TransferManager::next_continuation_token(Some(false), Some("tok".to_string())), None);
+        assert_eq!(S3TransferManager::next_continuation_token(None, Some("tok".to_string())), None);
     }
-    */
 }
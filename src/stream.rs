@@ -1,4 +1,6 @@
+use std::cell::RefCell;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::Poll;
 use bytes::Bytes;
 use aws_smithy_types::byte_stream::{ByteStream, error::Error};
@@ -6,6 +8,7 @@ use aws_smithy_types::byte_stream::{ByteStream, error::Error};
 pub(crate) struct ByteStreamProgress<'a> {
     inner: ByteStream,
     progress_callback: Option<&'a dyn Fn(usize)>,
+    checksum: Option<Rc<RefCell<md5::Context>>>,
 }
 
 impl<'a> ByteStreamProgress<'a> {
@@ -13,9 +16,18 @@ impl<'a> ByteStreamProgress<'a> {
         Self {
             inner: stream,
             progress_callback: progress_callback,
+            checksum: None,
         }
     }
 
+    // attaches a shared MD5 context that is fed every chunk as it passes
+    // through; the caller keeps its own handle to read the digest once the
+    // stream is fully consumed
+    pub fn with_checksum(mut self, checksum: Rc<RefCell<md5::Context>>) -> Self {
+        self.checksum = Some(checksum);
+        self
+    }
+
     #[allow(dead_code)]
     pub async fn next(&mut self) -> Option<Result<Bytes, Error>> {
         let res = self.inner.next().await;
@@ -23,6 +35,9 @@ impl<'a> ByteStreamProgress<'a> {
             if let Some(cb) = &self.progress_callback {
                 (cb)(bytes.len());
             }
+            if let Some(checksum) = &self.checksum {
+                checksum.borrow_mut().consume(bytes);
+            }
         }
         return res;
     }
@@ -40,6 +55,9 @@ impl<'a> futures_core::stream::Stream for ByteStreamProgress<'a> {
                 if let Some(cb) = &self.progress_callback {
                     (cb)(bytes.len());
                 }
+                if let Some(checksum) = &self.checksum {
+                    checksum.borrow_mut().consume(&bytes);
+                }
                 return Poll::Ready(Some(Ok(bytes)));
             },
             Poll::Ready(Some(Err(e))) => {